@@ -1,23 +1,81 @@
 //! Rust client library for [basket](https://github.com/mozmeao/basket/)
 //! Documentation can be found at [http://basket.readthedocs.org/].
-use failure::Error;
-use failure::Fail;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
 use serde_json::Value;
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use thiserror::Error;
 use url::Url;
 
-#[derive(Fail, Debug)]
+pub mod models;
+
+use models::{ApiResult, Newsletter, NewslettersResponse, User};
+
+/// Errors returned by the basket client.
+#[derive(Error, Debug)]
 pub enum BasketError {
-    #[fail(display = "token must be a uuid")]
+    #[error("token must be a uuid")]
     InvalidTokenFormat,
+    /// Basket responded with `{"status": "error"}` and a numeric `code` /
+    /// `desc`, e.g. an unknown token or an unknown email.
+    #[error("basket error {code}: {desc}")]
+    Api { code: i32, desc: String },
+    /// Basket responded with a non-2xx HTTP status.
+    #[error("basket responded with http status {status}")]
+    Http { status: u16 },
+    #[error("request to basket failed: {0}")]
+    Network(#[from] reqwest::Error),
+    #[error("failed to parse basket response: {0}")]
+    Deserialize(#[from] serde_json::Error),
+    #[error(transparent)]
+    UrlParse(#[from] url::ParseError),
+    #[error("failed to read/write basket config: {0}")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "toml-file")]
+    #[error("failed to parse toml basket config: {0}")]
+    TomlDe(#[from] toml::de::Error),
+    #[cfg(feature = "toml-file")]
+    #[error("failed to serialize toml basket config: {0}")]
+    TomlSer(#[from] toml::ser::Error),
+}
+
+impl From<ApiResponse> for BasketError {
+    fn from(r: ApiResponse) -> Self {
+        let desc = match r.desc {
+            Some(desc) => desc,
+            None => r.status.to_string(),
+        };
+        BasketError::Api {
+            code: r.code.unwrap_or_default(),
+            desc,
+        }
+    }
+}
+
+/// Controls how [`Basket`] retries transient HTTP failures (429/5xx).
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
 }
 
-#[serde(rename_all = "lowercase")]
 #[derive(Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
 pub enum Status {
     Ok,
     Error,
@@ -31,49 +89,30 @@ impl fmt::Display for Status {
     }
 }
 
+#[derive(Deserialize, Debug)]
 #[serde(rename_all = "lowercase")]
-#[derive(Deserialize, Debug, Fail)]
 pub struct ApiResponse {
     pub status: Status,
+    pub code: Option<i32>,
+    pub desc: Option<String>,
     #[serde(flatten)]
     pub data: Value,
 }
 
-impl fmt::Display for ApiResponse {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.status {
-            Status::Ok if !self.data.is_null() => {
-                write!(f, "{}", serde_json::to_string(&self.data).unwrap())
-            }
-            _ => write!(f, "{}", self.status),
-        }
-    }
-}
-
-#[derive(Serialize)]
+#[derive(Serialize, Default)]
 pub enum Format {
+    #[default]
     H,
     T,
 }
 
-impl Default for Format {
-    fn default() -> Self {
-        Self::H
-    }
-}
-
-#[derive(Serialize)]
+#[derive(Serialize, Default)]
 pub enum YesNo {
     Y,
+    #[default]
     N,
 }
 
-impl Default for YesNo {
-    fn default() -> Self {
-        Self::N
-    }
-}
-
 #[derive(Serialize)]
 pub struct Subscribe {
     pub email: String,
@@ -133,11 +172,86 @@ struct Recover {
     email: String,
 }
 
+/// Default freshness window for [`Basket::newsletters_cached`].
+const DEFAULT_NEWSLETTERS_TTL: Duration = Duration::from_secs(300);
+
+/// The last fetch time and value cached by [`Basket::newsletters_cached`].
+type NewslettersCache = Arc<RwLock<Option<(Instant, Vec<Newsletter>)>>>;
+
+/// Builds a [`Basket`], letting callers override the `reqwest::Client`
+/// (timeouts, proxy, user agent, ...) and the retry policy instead of
+/// living with [`Basket::new`]'s defaults.
+pub struct BasketBuilder {
+    api_key: String,
+    basket_url: Url,
+    client: Option<Client>,
+    timeout: Option<Duration>,
+    retry: RetryPolicy,
+}
+
+impl BasketBuilder {
+    pub fn new(api_key: impl Into<String>, basket_url: Url) -> Self {
+        BasketBuilder {
+            api_key: api_key.into(),
+            basket_url,
+            client: None,
+            timeout: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Use a pre-built `reqwest::Client` instead of constructing one from
+    /// `timeout`. Takes precedence over [`BasketBuilder::timeout`].
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Total per-request timeout (from connecting until the response body is
+    /// read) applied to the client built by [`BasketBuilder::build`].
+    /// Ignored if [`BasketBuilder::client`] was called.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn build(self) -> Result<Basket, BasketError> {
+        let client = match self.client {
+            Some(client) => client,
+            None => {
+                let mut builder = Client::builder();
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                builder.build()?
+            }
+        };
+
+        Ok(Basket {
+            api_key: Arc::new(self.api_key),
+            basket_url: Arc::new(self.basket_url),
+            client,
+            retry: self.retry,
+            newsletters_ttl: DEFAULT_NEWSLETTERS_TTL,
+            newsletters_cache: Arc::new(RwLock::new(None)),
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct Basket {
     pub api_key: Arc<String>,
     pub basket_url: Arc<Url>,
     pub client: Client,
+    retry: RetryPolicy,
+    /// Freshness window used by [`Basket::newsletters_cached`].
+    pub newsletters_ttl: Duration,
+    newsletters_cache: NewslettersCache,
 }
 
 impl Basket {
@@ -146,34 +260,158 @@ impl Basket {
             api_key: Arc::new(api_key.into()),
             basket_url: Arc::new(basket_url),
             client: Client::new(),
+            retry: RetryPolicy::default(),
+            newsletters_ttl: DEFAULT_NEWSLETTERS_TTL,
+            newsletters_cache: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Entry point for configuring the client, e.g. `Basket::builder(key, url).timeout(...).build()?`.
+    pub fn builder(api_key: impl Into<String>, basket_url: Url) -> BasketBuilder {
+        BasketBuilder::new(api_key, basket_url)
+    }
+
+    pub fn from_config(config: BasketConfig) -> Self {
+        Self::new(config.api_key, config.basket_url)
+    }
+
+    pub fn to_config(&self) -> BasketConfig {
+        BasketConfig {
+            api_key: (*self.api_key).clone(),
+            basket_url: (*self.basket_url).clone(),
         }
     }
+
+    /// Reads a [`BasketConfig`] from a JSON file, e.g. one written by
+    /// [`Basket::to_json_file`].
+    #[cfg(feature = "json-file")]
+    pub fn from_json_file(path: impl AsRef<std::path::Path>) -> Result<Self, BasketError> {
+        let data = std::fs::read_to_string(path)?;
+        let config: BasketConfig = serde_json::from_str(&data)?;
+        Ok(Self::from_config(config))
+    }
+
+    /// Writes this client's config as JSON, so it can be restored later via
+    /// [`Basket::from_json_file`] instead of re-entering credentials.
+    #[cfg(feature = "json-file")]
+    pub fn to_json_file(&self, path: impl AsRef<std::path::Path>) -> Result<(), BasketError> {
+        let data = serde_json::to_string_pretty(&self.to_config())?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+
+    /// Reads a [`BasketConfig`] from a TOML file, e.g. one written by
+    /// [`Basket::to_toml_file`].
+    #[cfg(feature = "toml-file")]
+    pub fn from_toml_file(path: impl AsRef<std::path::Path>) -> Result<Self, BasketError> {
+        let data = std::fs::read_to_string(path)?;
+        let config: BasketConfig = toml::from_str(&data)?;
+        Ok(Self::from_config(config))
+    }
+
+    /// Writes this client's config as TOML, so it can be restored later via
+    /// [`Basket::from_toml_file`] instead of re-entering credentials.
+    #[cfg(feature = "toml-file")]
+    pub fn to_toml_file(&self, path: impl AsRef<std::path::Path>) -> Result<(), BasketError> {
+        let data = toml::to_string_pretty(&self.to_config())?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+}
+
+/// A [`Basket`]'s connection details, serializable so applications can
+/// persist the basket endpoint and API key in a config file instead of
+/// reconstructing the client from environment variables every run.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BasketConfig {
+    pub api_key: String,
+    pub basket_url: Url,
 }
 
 impl Basket {
+    /// Sends `req`, retrying transient failures (429/5xx) with exponential
+    /// backoff per `self.retry`, honoring `Retry-After` when present.
+    async fn send(&self, req: RequestBuilder) -> Result<reqwest::Response, BasketError> {
+        let mut backoff = self.retry.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            let attempt_req = req
+                .try_clone()
+                .expect("basket request bodies are always buffered, not streamed");
+            let res = attempt_req.send().await?;
+            let status = res.status();
+            if status.is_success() {
+                return Ok(res);
+            }
+
+            let retryable = status.as_u16() == 429 || status.is_server_error();
+            if !retryable || attempt >= self.retry.max_retries {
+                // Basket's `status`/`code`/`desc` error envelope isn't
+                // limited to HTTP 200 responses — plenty of validation/auth
+                // failures come back with a non-2xx status and the same
+                // JSON body. Try to recover it before giving up with a bare
+                // HTTP status.
+                let body = res.text().await.unwrap_or_default();
+                return match serde_json::from_str::<ApiResponse>(&body) {
+                    Ok(r) => Err(r.into()),
+                    Err(_) => Err(BasketError::Http {
+                        status: status.as_u16(),
+                    }),
+                };
+            }
+
+            let wait = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(backoff);
+            tokio::time::sleep(wait).await;
+
+            backoff = (backoff * 2).min(self.retry.max_backoff);
+            attempt += 1;
+        }
+    }
+
+    /// Sends `req` and deserializes the basket envelope, giving callers the
+    /// raw `status`/`code`/`desc`/`data`. Used by the `*_raw` and `()`-returning
+    /// methods.
+    async fn request_api(&self, req: RequestBuilder) -> Result<ApiResponse, BasketError> {
+        let res = self.send(req).await?;
+        Ok(res.json::<ApiResponse>().await?)
+    }
+
+    /// Sends `req` and deserializes straight into `T`, falling back to
+    /// [`BasketError::Api`] on a basket error response.
+    async fn request<T: DeserializeOwned>(&self, req: RequestBuilder) -> Result<T, BasketError> {
+        let res = self.send(req).await?;
+        match res.json::<ApiResult<T>>().await? {
+            ApiResult::Ok(t) => Ok(t),
+            ApiResult::Err(e) => Err(e.into()),
+        }
+    }
+
     pub async fn subscribe(
         &self,
         email: impl Into<String>,
         newsletters: Vec<String>,
         opts: Option<SubscribeOpts>,
-    ) -> Result<(), Error> {
+    ) -> Result<(), BasketError> {
         let form = Subscribe {
             email: email.into(),
             newsletters: newsletters.join(","),
             opts,
         };
-
-        let res = self
+        let req = self
             .client
             .post(self.basket_url.join("/news/subscribe/")?)
-            .form(&form)
-            .send()
-            .await?;
-
-        match res.json::<ApiResponse>().await {
-            Ok(r) if r.status == Status::Ok => Ok(()),
-            Ok(r) => Err(r.into()),
-            Err(e) => Err(e.into()),
+            .form(&form);
+        let r = self.request_api(req).await?;
+        if r.status == Status::Ok {
+            Ok(())
+        } else {
+            Err(r.into())
         }
     }
 
@@ -182,25 +420,22 @@ impl Basket {
         email: impl Into<String>,
         newsletters: Vec<String>,
         opts: Option<SubscribeOpts>,
-    ) -> Result<(), Error> {
+    ) -> Result<(), BasketError> {
         let form = Subscribe {
             email: email.into(),
             newsletters: newsletters.join(","),
             opts,
         };
-
-        let res = self
+        let req = self
             .client
             .post(self.basket_url.join("/news/subscribe/")?)
             .query(&[("api-key", self.api_key.as_str())])
-            .form(&form)
-            .send()
-            .await?;
-
-        match res.json::<ApiResponse>().await {
-            Ok(r) if r.status == Status::Ok => Ok(()),
-            Ok(r) => Err(r.into()),
-            Err(e) => Err(e.into()),
+            .form(&form);
+        let r = self.request_api(req).await?;
+        if r.status == Status::Ok {
+            Ok(())
+        } else {
+            Err(r.into())
         }
     }
 
@@ -209,42 +444,46 @@ impl Basket {
         token: impl AsRef<str>,
         newsletters: Vec<String>,
         optout: YesNo,
-    ) -> Result<(), Error> {
+    ) -> Result<(), BasketError> {
         let form = Unsubscribe {
             newsletters: newsletters.join(","),
             optout,
         };
-
-        let res = self
+        let req = self
             .client
             .post(
                 self.basket_url
                     .join(&format!("/news/unsubscribe/{}/", token.as_ref()))?,
             )
-            .form(&form)
-            .send()
-            .await?;
-
-        match res.json::<ApiResponse>().await {
-            Ok(r) if r.status == Status::Ok => Ok(()),
-            Ok(r) => Err(r.into()),
-            Err(e) => Err(e.into()),
+            .form(&form);
+        let r = self.request_api(req).await?;
+        if r.status == Status::Ok {
+            Ok(())
+        } else {
+            Err(r.into())
         }
     }
 
-    pub async fn get_user(&self, token: impl AsRef<str>) -> Result<Value, Error> {
-        let res = self
-            .client
-            .get(
-                self.basket_url
-                    .join(&format!("/news/user/{}/", token.as_ref()))?,
-            )
-            .send()
-            .await?;
-        match res.json::<ApiResponse>().await {
-            Ok(r) if r.status == Status::Ok => Ok(r.data),
-            Ok(r) => Err(r.into()),
-            Err(e) => Err(e.into()),
+    pub async fn get_user(&self, token: impl AsRef<str>) -> Result<User, BasketError> {
+        let req = self.client.get(
+            self.basket_url
+                .join(&format!("/news/user/{}/", token.as_ref()))?,
+        );
+        self.request(req).await
+    }
+
+    /// Like [`Basket::get_user`] but returns the raw JSON payload, for
+    /// fields [`User`] doesn't model yet.
+    pub async fn get_user_raw(&self, token: impl AsRef<str>) -> Result<Value, BasketError> {
+        let req = self.client.get(
+            self.basket_url
+                .join(&format!("/news/user/{}/", token.as_ref()))?,
+        );
+        let r = self.request_api(req).await?;
+        if r.status == Status::Ok {
+            Ok(r.data)
+        } else {
+            Err(r.into())
         }
     }
 
@@ -253,92 +492,149 @@ impl Basket {
         email: impl Into<String>,
         token: impl AsRef<str>,
         opts: Option<UpdateUserOpts>,
-    ) -> Result<(), Error> {
+    ) -> Result<(), BasketError> {
         let form = UpdateUser {
             email: Some(email.into()),
             opts,
         };
-        let res = self
+        let req = self
             .client
             .post(
                 self.basket_url
                     .join(&format!("/news/user/{}/", token.as_ref()))?,
             )
-            .form(&form)
-            .send()
-            .await?;
-        match res.json::<ApiResponse>().await {
-            Ok(r) if r.status == Status::Ok => Ok(()),
-            Ok(r) => Err(r.into()),
-            Err(e) => Err(e.into()),
+            .form(&form);
+        let r = self.request_api(req).await?;
+        if r.status == Status::Ok {
+            Ok(())
+        } else {
+            Err(r.into())
         }
     }
 
-    pub async fn newsletters(&self) -> Result<Value, Error> {
-        let res = self
-            .client
-            .get(self.basket_url.join("/news/newsletters/")?)
-            .send()
-            .await?;
-        match res.json::<ApiResponse>().await {
-            Ok(r) if r.status == Status::Ok => Ok(r.data),
-            Ok(r) => Err(r.into()),
-            Err(e) => Err(e.into()),
+    pub async fn newsletters(&self) -> Result<Vec<Newsletter>, BasketError> {
+        let req = self.client.get(self.basket_url.join("/news/newsletters/")?);
+        let r: NewslettersResponse = self.request(req).await?;
+        Ok(r.newsletters.into_values().collect())
+    }
+
+    /// Like [`Basket::newsletters`] but returns the raw JSON payload, for
+    /// fields [`Newsletter`] doesn't model yet.
+    pub async fn newsletters_raw(&self) -> Result<Value, BasketError> {
+        let req = self.client.get(self.basket_url.join("/news/newsletters/")?);
+        let r = self.request_api(req).await?;
+        if r.status == Status::Ok {
+            Ok(r.data)
+        } else {
+            Err(r.into())
+        }
+    }
+
+    /// Like [`Basket::newsletters`], but serves from an in-memory cache when
+    /// the last fetch is still within `newsletters_ttl`, and refreshes it
+    /// transparently otherwise. The catalog rarely changes, so this keeps
+    /// repeated lookups network-free. A cache hit returns a clone of the
+    /// cached list, so each call still allocates.
+    pub async fn newsletters_cached(&self) -> Result<Vec<Newsletter>, BasketError> {
+        if let Some((fetched_at, newsletters)) = self.newsletters_cache.read().unwrap().as_ref() {
+            if fetched_at.elapsed() < self.newsletters_ttl {
+                return Ok(newsletters.clone());
+            }
         }
+
+        let newsletters = self.newsletters().await?;
+        *self.newsletters_cache.write().unwrap() = Some((Instant::now(), newsletters.clone()));
+        Ok(newsletters)
+    }
+
+    /// Forces the next [`Basket::newsletters_cached`] call to refetch the catalog.
+    pub fn invalidate_newsletters(&self) {
+        *self.newsletters_cache.write().unwrap() = None;
     }
 
     pub async fn debug_user(
         &self,
-        email: impl AsRef<str>,
-        supertoken: impl AsRef<str>,
-    ) -> Result<Value, Error> {
-        let res = self
+        email: impl Into<String>,
+        supertoken: impl Into<String>,
+    ) -> Result<User, BasketError> {
+        let query = DebugUser {
+            email: email.into(),
+            supertoken: supertoken.into(),
+        };
+        let req = self
+            .client
+            .get(self.basket_url.join("/news/debug-user/")?)
+            .query(&query);
+        self.request(req).await
+    }
+
+    /// Like [`Basket::debug_user`] but returns the raw JSON payload, for
+    /// fields [`User`] doesn't model yet.
+    pub async fn debug_user_raw(
+        &self,
+        email: impl Into<String>,
+        supertoken: impl Into<String>,
+    ) -> Result<Value, BasketError> {
+        let query = DebugUser {
+            email: email.into(),
+            supertoken: supertoken.into(),
+        };
+        let req = self
             .client
             .get(self.basket_url.join("/news/debug-user/")?)
-            .query(&[
-                ("email", email.as_ref()),
-                ("supertoken", supertoken.as_ref()),
-            ])
-            .send()
-            .await?;
-        match res.json::<ApiResponse>().await {
-            Ok(r) if r.status == Status::Ok => Ok(r.data),
-            Ok(r) => Err(r.into()),
-            Err(e) => Err(e.into()),
+            .query(&query);
+        let r = self.request_api(req).await?;
+        if r.status == Status::Ok {
+            Ok(r.data)
+        } else {
+            Err(r.into())
         }
     }
 
-    pub async fn lookup_user(&self, email: impl AsRef<str>) -> Result<Value, Error> {
-        let res = self
+    pub async fn lookup_user(&self, email: impl Into<String>) -> Result<User, BasketError> {
+        let query = LookupUser {
+            email: email.into(),
+            api_key: (*self.api_key).clone(),
+        };
+        let req = self
+            .client
+            .get(self.basket_url.join("/news/lookup-user/")?)
+            .query(&query);
+        self.request(req).await
+    }
+
+    /// Like [`Basket::lookup_user`] but returns the raw JSON payload, for
+    /// fields [`User`] doesn't model yet.
+    pub async fn lookup_user_raw(&self, email: impl Into<String>) -> Result<Value, BasketError> {
+        let query = LookupUser {
+            email: email.into(),
+            api_key: (*self.api_key).clone(),
+        };
+        let req = self
             .client
             .get(self.basket_url.join("/news/lookup-user/")?)
-            .query(&[
-                ("email", email.as_ref()),
-                ("api-key", self.api_key.as_str()),
-            ])
-            .send()
-            .await?;
-        match res.json::<ApiResponse>().await {
-            Ok(r) if r.status == Status::Ok => Ok(r.data),
-            Ok(r) => Err(r.into()),
-            Err(e) => Err(e.into()),
+            .query(&query);
+        let r = self.request_api(req).await?;
+        if r.status == Status::Ok {
+            Ok(r.data)
+        } else {
+            Err(r.into())
         }
     }
 
-    pub async fn recover(&self, email: impl Into<String>) -> Result<(), Error> {
+    pub async fn recover(&self, email: impl Into<String>) -> Result<(), BasketError> {
         let form = Recover {
             email: email.into(),
         };
-        let res = self
+        let req = self
             .client
             .post(self.basket_url.join("/news/recover/")?)
-            .form(&form)
-            .send()
-            .await?;
-        match res.json::<ApiResponse>().await {
-            Ok(r) if r.status == Status::Ok => Ok(()),
-            Ok(r) => Err(r.into()),
-            Err(e) => Err(e.into()),
+            .form(&form);
+        let r = self.request_api(req).await?;
+        if r.status == Status::Ok {
+            Ok(())
+        } else {
+            Err(r.into())
         }
     }
 }
@@ -347,9 +643,11 @@ impl Basket {
 mod test {
     use super::*;
     use std::env::var;
+    use std::io::{Read, Write};
+    use std::net::{Shutdown, TcpListener};
 
     #[tokio::test]
-    async fn recover() -> Result<(), Error> {
+    async fn recover() -> Result<(), BasketError> {
         let basket =
             if let (Ok(api_key), Ok(basket_url)) = (var("BASKET_API_KEY"), var("BASKET_URL")) {
                 Basket::new(api_key, Url::parse(&basket_url)?)
@@ -360,4 +658,156 @@ mod test {
         basket.recover("foo@bar.com").await?;
         Ok(())
     }
+
+    /// Spins up a plain-HTTP listener on 127.0.0.1 that replies to incoming
+    /// connections with `responses` in order, one connection per response,
+    /// then returns the base URL to hit it at. Used to exercise behavior
+    /// (caching, retries) that needs a real round trip without a mock server
+    /// dependency.
+    fn mock_server(responses: Vec<(u16, &'static str)>) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let response = format!(
+                    "HTTP/1.1 {status} x\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+                let _ = stream.shutdown(Shutdown::Write);
+            }
+        });
+        Url::parse(&format!("http://{}/", addr)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn newsletters_cached_serves_from_cache_until_ttl_expires() {
+        let resp1 = r#"{"newsletters": {"n1": {"slug": "n1", "title": "N1", "active": true}}}"#;
+        let resp2 = r#"{"newsletters": {"n2": {"slug": "n2", "title": "N2", "active": true}}}"#;
+        let basket_url = mock_server(vec![(200, resp1), (200, resp2)]);
+        let mut basket = Basket::new("key", basket_url);
+        basket.newsletters_ttl = Duration::from_millis(20);
+
+        let first = basket.newsletters_cached().await.unwrap();
+        assert_eq!(first[0].slug, "n1");
+
+        // Still within the TTL: served from cache, no second request made.
+        let second = basket.newsletters_cached().await.unwrap();
+        assert_eq!(second[0].slug, "n1");
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        let third = basket.newsletters_cached().await.unwrap();
+        assert_eq!(third[0].slug, "n2");
+    }
+
+    #[tokio::test]
+    async fn invalidate_newsletters_forces_refetch() {
+        let resp1 = r#"{"newsletters": {"n1": {"slug": "n1", "title": "N1", "active": true}}}"#;
+        let resp2 = r#"{"newsletters": {"n2": {"slug": "n2", "title": "N2", "active": true}}}"#;
+        let basket_url = mock_server(vec![(200, resp1), (200, resp2)]);
+        let basket = Basket::new("key", basket_url);
+
+        let first = basket.newsletters_cached().await.unwrap();
+        assert_eq!(first[0].slug, "n1");
+
+        basket.invalidate_newsletters();
+
+        let second = basket.newsletters_cached().await.unwrap();
+        assert_eq!(second[0].slug, "n2");
+    }
+
+    fn fast_retry_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 1,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn send_retries_429_then_succeeds() {
+        let basket_url = mock_server(vec![(429, ""), (200, r#"{"status": "ok"}"#)]);
+        let basket = Basket::builder("key", basket_url)
+            .retry_policy(fast_retry_policy())
+            .build()
+            .unwrap();
+
+        basket.recover("foo@bar.com").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn send_exhausts_retries_and_recovers_code_desc() {
+        let body = r#"{"status": "error", "code": 7, "desc": "boom"}"#;
+        let basket_url = mock_server(vec![(500, body), (500, body)]);
+        let basket = Basket::builder("key", basket_url)
+            .retry_policy(fast_retry_policy())
+            .build()
+            .unwrap();
+
+        let err = basket.recover("foo@bar.com").await.unwrap_err();
+        match err {
+            BasketError::Api { code, desc } => {
+                assert_eq!(code, 7);
+                assert_eq!(desc, "boom");
+            }
+            e => panic!("expected BasketError::Api, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn api_response_with_desc_converts_to_basket_error() {
+        let r: ApiResponse =
+            serde_json::from_str(r#"{"status": "error", "code": 3, "desc": "unknown email"}"#)
+                .unwrap();
+        let err: BasketError = r.into();
+        match err {
+            BasketError::Api { code, desc } => {
+                assert_eq!(code, 3);
+                assert_eq!(desc, "unknown email");
+            }
+            e => panic!("expected BasketError::Api, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn api_response_without_desc_or_code_falls_back_to_status() {
+        let r: ApiResponse = serde_json::from_str(r#"{"status": "error"}"#).unwrap();
+        let err: BasketError = r.into();
+        match err {
+            BasketError::Api { code, desc } => {
+                assert_eq!(code, 0);
+                assert_eq!(desc, "error");
+            }
+            e => panic!("expected BasketError::Api, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn basket_config_json_round_trip() {
+        let config = BasketConfig {
+            api_key: "key".to_string(),
+            basket_url: Url::parse("https://basket.example.com").unwrap(),
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        let roundtripped: BasketConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(roundtripped.api_key, config.api_key);
+        assert_eq!(roundtripped.basket_url, config.basket_url);
+    }
+
+    #[cfg(feature = "toml-file")]
+    #[test]
+    fn basket_config_toml_round_trip() {
+        let config = BasketConfig {
+            api_key: "key".to_string(),
+            basket_url: Url::parse("https://basket.example.com").unwrap(),
+        };
+        let toml = toml::to_string(&config).unwrap();
+        let roundtripped: BasketConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(roundtripped.api_key, config.api_key);
+        assert_eq!(roundtripped.basket_url, config.basket_url);
+    }
 }
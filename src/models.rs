@@ -0,0 +1,100 @@
+//! Typed response models for the basket API.
+//!
+//! Basket's JSON responses are either a success payload or an error shape
+//! (`{"status": "error", ...}`). [`ApiResult`] lets callers deserialize
+//! straight into the success struct they expect, falling back to
+//! [`crate::ApiResponse`] when the API reports a failure.
+use std::collections::HashMap;
+
+use serde_derive::Deserialize;
+use serde_json::Value;
+
+use crate::ApiResponse;
+
+/// A basket user record, as returned by `get_user`, `lookup_user`, and `debug_user`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct User {
+    pub email: String,
+    pub token: String,
+    pub lang: Option<String>,
+    pub country: Option<String>,
+    #[serde(default)]
+    pub newsletters: Vec<String>,
+    /// Fields the basket API returns that aren't modeled above yet.
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// A single newsletter as listed by `/news/newsletters/`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Newsletter {
+    pub slug: String,
+    pub title: String,
+    pub active: bool,
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Value,
+}
+
+/// Raw shape of the `/news/newsletters/` response: a map keyed by slug.
+#[derive(Deserialize, Debug, Clone)]
+pub struct NewslettersResponse {
+    pub newsletters: HashMap<String, Newsletter>,
+}
+
+/// Deserializes a basket response into either the expected success shape
+/// or the basket error envelope ([`crate::ApiResponse`]), trying the
+/// success variant first.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum ApiResult<T> {
+    Ok(T),
+    Err(ApiResponse),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Status;
+
+    #[test]
+    fn user_deserializes_known_and_extra_fields() {
+        let user: User = serde_json::from_str(
+            r#"{"email": "a@b.com", "token": "tok", "lang": "en", "country": "us",
+                "newsletters": ["n1", "n2"], "extra_field": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(user.email, "a@b.com");
+        assert_eq!(user.newsletters, vec!["n1", "n2"]);
+        assert_eq!(user.extra["extra_field"], 1);
+    }
+
+    #[test]
+    fn newsletter_defaults_missing_languages() {
+        let newsletter: Newsletter =
+            serde_json::from_str(r#"{"slug": "s", "title": "t", "active": true}"#).unwrap();
+        assert!(newsletter.languages.is_empty());
+    }
+
+    #[test]
+    fn api_result_deserializes_success_before_error() {
+        let ok: ApiResult<User> = serde_json::from_str(
+            r#"{"status": "ok", "email": "a@b.com", "token": "tok", "lang": null, "country": null}"#,
+        )
+        .unwrap();
+        assert!(matches!(ok, ApiResult::Ok(_)));
+
+        let err: ApiResult<User> =
+            serde_json::from_str(r#"{"status": "error", "code": 3, "desc": "unknown email"}"#)
+                .unwrap();
+        match err {
+            ApiResult::Err(r) => {
+                assert_eq!(r.status, Status::Error);
+                assert_eq!(r.code, Some(3));
+                assert_eq!(r.desc.as_deref(), Some("unknown email"));
+            }
+            ApiResult::Ok(_) => panic!("expected ApiResult::Err"),
+        }
+    }
+}